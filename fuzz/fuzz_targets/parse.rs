@@ -0,0 +1,32 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use snipper::parsing::{parse_includes, parse_snippets};
+
+/// Drive the pure snippet/include parsers with arbitrary text, the same way
+/// `parsing::tests::parsing_never_panics` does with bounded `proptest`
+/// strings, but with libfuzzer-generated inputs instead of proptest's
+/// 512-character bound.
+fuzz_target!(|text: &str| {
+    let active = regex::RegexBuilder::new(
+        r"(// SNIPPET:BEGIN \{(?P<BEGIN>.*?)\}(\$\{(?P<COMMENT>.*?)\})?(?P<SNIPPET>.*?)// SNIPPET:END \{(?P<END>.*?)\})",
+    )
+    .dot_matches_new_line(true)
+    .build()
+    .unwrap();
+
+    let inactive = regex::RegexBuilder::new(
+        r"(// _SNIPPET:BEGIN \{(?P<BEGIN>.*?)\}(\$\{(?P<COMMENT>.*?)\})?(?P<SNIPPET>.*?)// _SNIPPET:END \{(?P<END>.*?)\})",
+    )
+    .dot_matches_new_line(true)
+    .build()
+    .unwrap();
+
+    let include =
+        regex::RegexBuilder::new(r"(\\lstinputlisting.*?\{.*/(?P<SNIPPET_NAME>.*?)\.cpp.*?\})")
+            .build()
+            .unwrap();
+
+    let _ = parse_snippets(text, &active, &inactive, None, Some("cpp"));
+    let _ = parse_includes(text, &include);
+});
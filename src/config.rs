@@ -0,0 +1,169 @@
+use std::{fs, path::Path};
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+/// The name of the configuration file Snipper looks for in the source root.
+const CONFIG_FILE_NAME: &str = "snipper.toml";
+
+/// One language's snippet conventions: which file extensions it applies to,
+/// what its comment tokens look like, and which extensions the extracted
+/// snippet file and its LaTeX `\lstinputlisting` include should use.
+///
+/// `snippet_pattern_active`/`snippet_pattern_inactive` are built from
+/// `line_comment`/`block_comment` per profile, rather than from the fixed
+/// `// SNIPPET:BEGIN`/`END` string literals Snipper used to assume.
+#[derive(Debug, Clone, Deserialize)]
+pub struct LanguageProfile {
+    pub name: String,
+    pub extensions: Vec<String>,
+    pub line_comment: Option<String>,
+    pub block_comment: Option<(String, String)>,
+    #[serde(default = "default_extracted_extension")]
+    pub extracted_extension: String,
+    /// Extension the LaTeX `\lstinputlisting` include should look for.
+    /// Defaults to mirroring `extracted_extension`, since profiles almost
+    /// always want the include and the extracted file to match; set this
+    /// only when they need to differ.
+    pub latex_extension: Option<String>,
+    /// Text inserted between fragments of a multi-region snippet on
+    /// extraction. Defaults to a blank line; set to something like
+    /// `// ...` for an ellipsis comment instead.
+    pub fragment_separator: Option<String>,
+}
+
+fn default_extracted_extension() -> String {
+    "cpp".into()
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Config {
+    #[serde(rename = "profile", default = "default_profiles")]
+    pub profiles: Vec<LanguageProfile>,
+}
+
+fn default_profiles() -> Vec<LanguageProfile> {
+    vec![LanguageProfile {
+        name: "cpp".into(),
+        extensions: vec!["cpp".into(), "h".into()],
+        line_comment: Some("//".into()),
+        block_comment: None,
+        extracted_extension: "cpp".into(),
+        latex_extension: None,
+        fragment_separator: None,
+    }]
+}
+
+/// Blank line inserted between a multi-region snippet's fragments when no
+/// profile-specific `fragment_separator` is configured.
+const DEFAULT_FRAGMENT_SEPARATOR: &str = "\n";
+
+impl Config {
+    /// Find the profile that claims `extension`, if any.
+    pub fn profile_for_extension(&self, extension: &str) -> Option<&LanguageProfile> {
+        self.profiles
+            .iter()
+            .find(|profile| profile.extensions.iter().any(|ext| ext == extension))
+    }
+
+    /// Find the profile whose extracted-file extension matches `extension`.
+    pub fn profile_for_extracted_extension(&self, extension: &str) -> Option<&LanguageProfile> {
+        self.profiles
+            .iter()
+            .find(|profile| profile.extracted_extension == extension)
+    }
+
+    /// Load `snipper.toml` from `source_directory`, falling back to the
+    /// original C++ `//`-comment profile when no config file is present.
+    pub fn load(source_directory: &Path) -> Result<Self> {
+        let config_path = source_directory.join(CONFIG_FILE_NAME);
+        if !config_path.is_file() {
+            return Ok(Self {
+                profiles: default_profiles(),
+            });
+        }
+
+        let text = fs::read_to_string(&config_path)
+            .context(format!("Unable to read config file {:?}", config_path))?;
+        toml::from_str(&text).context(format!("Unable to parse config file {:?}", config_path))
+    }
+}
+
+impl LanguageProfile {
+    /// Text to join this profile's snippet fragments with on extraction.
+    pub fn fragment_separator(&self) -> &str {
+        self.fragment_separator
+            .as_deref()
+            .unwrap_or(DEFAULT_FRAGMENT_SEPARATOR)
+    }
+
+    /// Extension this profile's LaTeX includes look for, mirroring
+    /// `extracted_extension` when no override was configured.
+    pub fn latex_extension(&self) -> &str {
+        self.latex_extension
+            .as_deref()
+            .unwrap_or(&self.extracted_extension)
+    }
+
+    /// This profile's comment tokens as an (open, close) pair: the line
+    /// comment with an empty close, or (when no line comment is configured)
+    /// the block comment pair, or `//` when neither is given.
+    fn comment_tokens(&self) -> (&str, &str) {
+        match (self.line_comment.as_deref(), self.block_comment.as_ref()) {
+            (Some(line), _) => (line, ""),
+            (None, Some((open, close))) => (open.as_str(), close.as_str()),
+            (None, None) => ("//", ""),
+        }
+    }
+
+    /// Wrap `tag` (e.g. `SNIPPET:BEGIN {name}`) in this profile's comment
+    /// tokens, the way `cmd_new`/`cmd_add` scaffold markers into source
+    /// files. A block comment is closed right after the tag, which is the
+    /// form `snippet_patterns` matches.
+    pub fn wrap_tag(&self, tag: &str) -> String {
+        let (open, close) = self.comment_tokens();
+        if close.is_empty() {
+            format!("{} {}", open, tag)
+        } else {
+            format!("{} {}{}", open, tag, close)
+        }
+    }
+
+    /// Build the active/inactive `SNIPPET:BEGIN`/`END` regexes for this
+    /// profile's comment tokens: a line comment, or (when none is
+    /// configured) a block comment pair closed right after each tag,
+    /// falling back to `//` when neither is given.
+    pub fn snippet_patterns(&self) -> Result<(regex::Regex, regex::Regex)> {
+        let (open, close) = self.comment_tokens();
+        let open = regex::escape(open);
+        let close = regex::escape(close);
+
+        let active = regex::RegexBuilder::new(&format!(
+            r"({open} SNIPPET:BEGIN \{{(?P<BEGIN>.*?)\}}(\$\{{(?P<COMMENT>.*?)\}})?{close}(?P<SNIPPET>.*?){open} SNIPPET:END \{{(?P<END>.*?)\}}{close})"
+        ))
+        .dot_matches_new_line(true)
+        .build()
+        .context("Unable to build active snippet pattern.")?;
+
+        let inactive = regex::RegexBuilder::new(&format!(
+            r"({open} _SNIPPET:BEGIN \{{(?P<BEGIN>.*?)\}}(\$\{{(?P<COMMENT>.*?)\}})?{close}(?P<SNIPPET>.*?){open} _SNIPPET:END \{{(?P<END>.*?)\}}{close})"
+        ))
+        .dot_matches_new_line(true)
+        .build()
+        .context("Unable to build inactive snippet pattern.")?;
+
+        Ok((active, inactive))
+    }
+
+    /// Build the `\lstinputlisting{...name.ext}` include pattern for this
+    /// profile's LaTeX extension.
+    pub fn include_pattern(&self) -> Result<regex::Regex> {
+        let extension = regex::escape(self.latex_extension());
+        regex::RegexBuilder::new(&format!(
+            r"(\\lstinputlisting.*?\{{.*/(?P<SNIPPET_NAME>.*?)\.{extension}.*?\}})"
+        ))
+        .dot_matches_new_line(false)
+        .build()
+        .context("Unable to build include pattern.")
+    }
+}
@@ -0,0 +1,172 @@
+use anyhow::{Context, Result};
+
+/// Characters that need a backslash to be matched literally inside a regex,
+/// indexed directly by byte value instead of branching on a match arm.
+const fn escape_table() -> [bool; 256] {
+    let mut table = [false; 256];
+    let mut i = 0;
+    while i < SPECIAL_BYTES.len() {
+        table[SPECIAL_BYTES[i] as usize] = true;
+        i += 1;
+    }
+    table
+}
+
+const SPECIAL_BYTES: &[u8] = b"()[]{}?*+-|^$\\.&~# \t\n\r";
+const ESCAPE_TABLE: [bool; 256] = escape_table();
+
+/// Translate a single glob pattern into an anchored regex source string.
+///
+/// Scans left to right, at each position preferring the first pattern that
+/// matches, in priority order: `*/` -> `(?:.*/)?`, `**` -> `.*`,
+/// `*` -> `[^/]*`, `?` -> `[^/]`, otherwise the literal character (escaped via
+/// `ESCAPE_TABLE` when it's a regex metacharacter).
+fn translate(pattern: &str) -> String {
+    let chars: Vec<char> = pattern.chars().collect();
+    let mut regex = String::from("^");
+    let mut i = 0;
+
+    while i < chars.len() {
+        if chars[i] == '*' && chars.get(i + 1) == Some(&'/') {
+            regex.push_str("(?:.*/)?");
+            i += 2;
+        } else if chars[i] == '*' && chars.get(i + 1) == Some(&'*') {
+            regex.push_str(".*");
+            i += 2;
+        } else if chars[i] == '*' {
+            regex.push_str("[^/]*");
+            i += 1;
+        } else if chars[i] == '?' {
+            regex.push_str("[^/]");
+            i += 1;
+        } else {
+            let c = chars[i];
+            if c.is_ascii() && ESCAPE_TABLE[c as usize] {
+                regex.push('\\');
+            }
+            regex.push(c);
+            i += 1;
+        }
+    }
+
+    regex.push('$');
+    regex
+}
+
+/// Compile a glob pattern into a `Regex` that matches a forward-slash
+/// normalized path.
+pub fn compile(pattern: &str) -> Result<regex::Regex> {
+    regex::Regex::new(&translate(pattern)).context(format!("Invalid glob pattern \"{}\"", pattern))
+}
+
+/// Normalize a path to forward slashes so globs behave the same on Windows
+/// and Unix.
+pub fn normalize_path(path: &std::path::Path) -> String {
+    path.to_string_lossy().replace('\\', "/")
+}
+
+/// Compiled include/exclude glob patterns for narrowing a directory walk.
+pub struct Filters {
+    include: Vec<regex::Regex>,
+    exclude: Vec<regex::Regex>,
+}
+
+impl Filters {
+    pub fn new(include: &[String], exclude: &[String]) -> Result<Self> {
+        Ok(Self {
+            include: include
+                .iter()
+                .map(|pattern| compile(pattern))
+                .collect::<Result<_>>()?,
+            exclude: exclude
+                .iter()
+                .map(|pattern| compile(pattern))
+                .collect::<Result<_>>()?,
+        })
+    }
+
+    /// True when no include/exclude patterns were given, i.e. callers should
+    /// fall back to their own extension check.
+    pub fn is_empty(&self) -> bool {
+        self.include.is_empty() && self.exclude.is_empty()
+    }
+
+    /// A path is kept if it matches any include pattern (or none were given)
+    /// and matches no exclude pattern.
+    pub fn matches(&self, path: &std::path::Path) -> bool {
+        let path = normalize_path(path);
+        let included =
+            self.include.is_empty() || self.include.iter().any(|pattern| pattern.is_match(&path));
+        let excluded = self.exclude.iter().any(|pattern| pattern.is_match(&path));
+        included && !excluded
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(pattern: &str, path: &str) -> bool {
+        compile(pattern).unwrap().is_match(path)
+    }
+
+    #[test]
+    fn metacharacters_are_escaped_literally() {
+        assert!(matches("a.b", "a.b"));
+        assert!(!matches("a.b", "axb"));
+        assert!(matches("a(b)[c]", "a(b)[c]"));
+        assert!(matches("a+b", "a+b"));
+    }
+
+    #[test]
+    fn star_slash_matches_zero_or_more_leading_directories() {
+        assert!(matches("*/foo.rs", "foo.rs"));
+        assert!(matches("*/foo.rs", "src/foo.rs"));
+        assert!(matches("*/foo.rs", "src/nested/foo.rs"));
+    }
+
+    #[test]
+    fn double_star_matches_across_path_separators() {
+        assert!(matches("src/**/foo.rs", "src/a/foo.rs"));
+        assert!(matches("src/**/foo.rs", "src/a/b/foo.rs"));
+        // Unlike `*/`, a bare `**` doesn't make its own surrounding slash
+        // optional, so it still requires a path segment in between.
+        assert!(!matches("src/**/foo.rs", "src/foo.rs"));
+    }
+
+    #[test]
+    fn single_star_does_not_cross_path_separators() {
+        assert!(matches("src/*.rs", "src/foo.rs"));
+        assert!(!matches("src/*.rs", "src/a/foo.rs"));
+    }
+
+    #[test]
+    fn question_mark_matches_exactly_one_non_separator_character() {
+        assert!(matches("foo.?s", "foo.rs"));
+        assert!(!matches("foo.?s", "foo.rss"));
+        assert!(!matches("foo.?s", "foo/rs"));
+    }
+
+    #[test]
+    fn filters_with_no_patterns_matches_everything() {
+        let filters = Filters::new(&[], &[]).unwrap();
+        assert!(filters.is_empty());
+        assert!(filters.matches(std::path::Path::new("anything.rs")));
+    }
+
+    #[test]
+    fn filters_keep_only_included_paths() {
+        let filters = Filters::new(&["*.rs".to_owned()], &[]).unwrap();
+        assert!(!filters.is_empty());
+        assert!(filters.matches(std::path::Path::new("foo.rs")));
+        assert!(!filters.matches(std::path::Path::new("foo.toml")));
+    }
+
+    #[test]
+    fn filters_exclude_overrides_include() {
+        let filters =
+            Filters::new(&["**/*.rs".to_owned()], &["**/generated/*.rs".to_owned()]).unwrap();
+        assert!(filters.matches(std::path::Path::new("src/foo.rs")));
+        assert!(!filters.matches(std::path::Path::new("src/generated/foo.rs")));
+    }
+}
@@ -0,0 +1,7 @@
+//! Library surface for Snipper's scanning logic, split out so the `fuzz`
+//! targets (and anything else that wants to drive the parsers directly) can
+//! depend on it without linking the `snipper` binary.
+
+pub mod config;
+pub mod glob;
+pub mod parsing;
@@ -1,4 +1,3 @@
-// TODO: Consider duplicate snippet tags in source files
 // TODO: Make output nicer for inactive snippets that are not overwritten
 
 #![feature(bool_to_option)]
@@ -11,260 +10,529 @@ use std::{
 };
 
 use anyhow::{Context, Result};
-use clap::{App, Arg};
+use clap::{App, Arg, ArgMatches, SubCommand};
 use walkdir::WalkDir;
 
-#[derive(Debug, PartialEq, Eq)]
-struct Snippet {
-    name: String,
-    content: Option<String>,
-    source_file: Option<PathBuf>,
-    comment: Option<String>,
-    active: bool,
-    source: bool,
-    latex: bool,
-    extracted: bool,
-}
+use snipper::{
+    config::{Config, LanguageProfile},
+    glob,
+    parsing::{parse_includes, parse_snippets, Snippet},
+};
 
-impl Ord for Snippet {
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        self.name.cmp(&other.name)
-    }
+/// The operations Snipper can be asked to perform. Each variant only carries
+/// the directories/paths it actually needs to run.
+enum Command {
+    /// Scan source/target/LaTeX directories and print a status report.
+    List {
+        source_directory: PathBuf,
+        target_directory: PathBuf,
+        latex_directory: PathBuf,
+        include: Vec<String>,
+        exclude: Vec<String>,
+    },
+    /// Same report as `List`, kept as its own entry point for callers that
+    /// think in terms of "status" rather than "listing".
+    Status {
+        source_directory: PathBuf,
+        target_directory: PathBuf,
+        latex_directory: PathBuf,
+        include: Vec<String>,
+        exclude: Vec<String>,
+    },
+    /// Write out the snippets found under `source_directory` into
+    /// `target_directory`. An active snippet's file is always written; an
+    /// inactive snippet's file is only created when none exists yet.
+    Extract {
+        source_directory: PathBuf,
+        target_directory: PathBuf,
+        include: Vec<String>,
+        exclude: Vec<String>,
+    },
+    /// Scaffold a fresh, empty `SNIPPET:BEGIN`/`END` pair into `file`, using
+    /// the comment style of whichever profile in `source_directory`'s config
+    /// claims `file`'s extension.
+    New {
+        source_directory: PathBuf,
+        file: PathBuf,
+        name: String,
+    },
+    /// Wrap the lines `start..=end` of `file` in a `SNIPPET:BEGIN`/`END` pair.
+    Add {
+        source_directory: PathBuf,
+        file: PathBuf,
+        name: String,
+        start: usize,
+        end: usize,
+    },
+    /// Delete extracted files (of any profile's extracted extension) in
+    /// `target_directory` whose name matches no active snippet in
+    /// `source_directory`.
+    Prune {
+        source_directory: PathBuf,
+        target_directory: PathBuf,
+    },
 }
 
-impl PartialOrd for Snippet {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        self.name.partial_cmp(&other.name)
-    }
+fn directory_arg(name: &'static str, help: &'static str) -> Arg<'static, 'static> {
+    Arg::with_name(name)
+        .long(name)
+        .value_name("DIRECTORY")
+        .takes_value(true)
+        .required(true)
+        .validator(move |path| {
+            Path::new(&path)
+                .is_dir()
+                .then_some(())
+                .ok_or_else(|| format!("Invalid {} directory.", name))
+        })
+        .help(help)
 }
 
-impl Snippet {
-    fn new(
-        begin: Option<&str>,
-        content: Option<&str>,
-        source_file: Option<PathBuf>,
-        end: Option<&str>,
-        comment: Option<&str>,
-        active: bool,
-        source: bool,
-        latex: bool,
-        extracted: bool,
-    ) -> Result<Self> {
-        let begin = begin.context("Snippet begin tag has no name.")?;
-        let end = end.context("Snippet end tag has no name.")?;
-
-        (begin == end)
-            .then_some(Self {
-                name: begin.to_owned(),
-                content: content.map(|string| string.to_owned()),
-                source_file,
-                comment: comment.map(|string| string.to_owned()),
-                active,
-                source,
-                latex,
-                extracted,
-            })
-            .context(format!(
-                "Snippet with mismatched begin and end tags\n\"{}\" != \"{}\"",
-                begin, end
-            ))
-    }
+fn glob_args() -> Vec<Arg<'static, 'static>> {
+    vec![
+        Arg::with_name("include")
+            .long("include")
+            .value_name("GLOB")
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1)
+            .help("Only scan paths matching this glob (may be given multiple times)"),
+        Arg::with_name("exclude")
+            .long("exclude")
+            .value_name("GLOB")
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1)
+            .help("Never scan paths matching this glob (may be given multiple times)"),
+    ]
 }
 
 fn main() {
-    let arguments = App::new("Snipper").about("Collects snippets of code from source files into separate files for simple inclusion in LaTeX documents.")
-    .arg(Arg::with_name("Source")
-        .long("Source").value_name("DIRECTORY")
-        .takes_value(true)
-        .required(true)
-        .validator(|path| Path::new(&path).is_dir().then_some(()).ok_or_else(||"Invalid source directory.".into()))
-        .help("Root directory of source files"))
-    .arg(Arg::with_name("Target")
-        .long("Target")
-        .value_name("DIRECTORY")
-        .takes_value(true)
-        .required(true)
-        .validator(|path| Path::new(&path).is_dir().then_some(()).ok_or_else(||"Invalid target directory.".into()))
-        .help("Directory, where snippets will be stored"))
-    .arg(Arg::with_name("LaTeX")
-        .long("LaTeX").value_name("DIRECTORY")
-        .takes_value(true)
-        .required(true)
-        .validator(|path| Path::new(&path).is_dir().then_some(()).ok_or_else(||"Invalid LaTeX directory.".into()))
-        .help("Root directory of LaTeX document"))
-    .arg(Arg::with_name("Extract")
-        .long("Extract")
-        .takes_value(false)
-        .case_insensitive(true)
-        .help("Extract found snippets into separate snippet files for inclusion in LaTeX document"))
+    let source_arg = directory_arg("Source", "Root directory of source files");
+    let target_arg = directory_arg("Target", "Directory, where snippets will be stored");
+    let latex_arg = directory_arg("LaTeX", "Root directory of LaTeX document");
+
+    let arguments = App::new("Snipper")
+        .about("Collects snippets of code from source files into separate files for simple inclusion in LaTeX documents.")
+        .subcommand(
+            SubCommand::with_name("list")
+                .about("List found snippets and their state")
+                .arg(source_arg.clone())
+                .arg(target_arg.clone())
+                .arg(latex_arg.clone())
+                .args(&glob_args()),
+        )
+        .subcommand(
+            SubCommand::with_name("status")
+                .about("Alias for `list`")
+                .arg(source_arg.clone())
+                .arg(target_arg.clone())
+                .arg(latex_arg.clone())
+                .args(&glob_args()),
+        )
+        .subcommand(
+            SubCommand::with_name("extract")
+                .about("Extract found snippets into separate snippet files")
+                .arg(source_arg.clone())
+                .arg(target_arg.clone())
+                .args(&glob_args()),
+        )
+        .subcommand(
+            SubCommand::with_name("new")
+                .about("Scaffold an empty SNIPPET:BEGIN/END pair into a source file")
+                .arg(source_arg.clone())
+                .arg(
+                    Arg::with_name("name")
+                        .required(true)
+                        .help("Name of the new snippet"),
+                )
+                .arg(
+                    Arg::with_name("file")
+                        .required(true)
+                        .validator(|path| {
+                            Path::new(&path)
+                                .is_file()
+                                .then_some(())
+                                .ok_or_else(|| "Invalid source file.".into())
+                        })
+                        .help("Source file to scaffold the snippet into"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("add")
+                .about("Wrap an existing region of a source file in a SNIPPET:BEGIN/END pair")
+                .arg(source_arg.clone())
+                .arg(
+                    Arg::with_name("name")
+                        .required(true)
+                        .help("Name of the snippet"),
+                )
+                .arg(
+                    Arg::with_name("file")
+                        .required(true)
+                        .validator(|path| {
+                            Path::new(&path)
+                                .is_file()
+                                .then_some(())
+                                .ok_or_else(|| "Invalid source file.".into())
+                        })
+                        .help("Source file containing the region"),
+                )
+                .arg(
+                    Arg::with_name("start")
+                        .long("start")
+                        .value_name("LINE")
+                        .takes_value(true)
+                        .required(true)
+                        .validator(|value| {
+                            value
+                                .parse::<usize>()
+                                .map(|_| ())
+                                .map_err(|e| e.to_string())
+                        })
+                        .help("First line of the region (1-indexed, inclusive)"),
+                )
+                .arg(
+                    Arg::with_name("end")
+                        .long("end")
+                        .value_name("LINE")
+                        .takes_value(true)
+                        .required(true)
+                        .validator(|value| {
+                            value
+                                .parse::<usize>()
+                                .map(|_| ())
+                                .map_err(|e| e.to_string())
+                        })
+                        .help("Last line of the region (1-indexed, inclusive)"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("prune")
+                .about("Delete extracted snippet files that match no active snippet in the source")
+                .arg(source_arg)
+                .arg(target_arg),
+        )
         .get_matches();
 
-    let source_directory = Path::new(arguments.value_of("Source").unwrap());
-    let target_directory = Path::new(arguments.value_of("Target").unwrap());
-    let latex_directory = Path::new(arguments.value_of("LaTeX").unwrap());
+    let command = match parse_command(&arguments) {
+        Ok(command) => command,
+        Err(error) => {
+            eprintln!("{:#}", error);
+            return;
+        }
+    };
+
+    let result = match command {
+        Command::List {
+            source_directory,
+            target_directory,
+            latex_directory,
+            include,
+            exclude,
+        }
+        | Command::Status {
+            source_directory,
+            target_directory,
+            latex_directory,
+            include,
+            exclude,
+        } => cmd_list(
+            &source_directory,
+            &target_directory,
+            &latex_directory,
+            &include,
+            &exclude,
+        ),
+        Command::Extract {
+            source_directory,
+            target_directory,
+            include,
+            exclude,
+        } => cmd_extract(&source_directory, &target_directory, &include, &exclude),
+        Command::New {
+            source_directory,
+            file,
+            name,
+        } => cmd_new(&source_directory, &file, &name),
+        Command::Add {
+            source_directory,
+            file,
+            name,
+            start,
+            end,
+        } => cmd_add(&source_directory, &file, &name, start, end),
+        Command::Prune {
+            source_directory,
+            target_directory,
+        } => cmd_prune(&source_directory, &target_directory),
+    };
+
+    if let Err(error) = result {
+        eprintln!("{:#}", error);
+    }
+}
 
-    let source_files =
-        files_with_extension(source_directory, vec!["cpp".into(), "h".into()], false);
-    let tex_files = files_with_extension(latex_directory, vec!["tex".into()], false);
-    let snippet_files = files_with_extension(target_directory, vec!["cpp".into()], false);
-
-    // Finding snippets tagged as follows:
-    /*
-    // SNIPPET:BEGIN {some_cool_snippet_name}${}    (the ${} part is optional - for comments/descriptions)
-    ...some_cool_snippet_content...
-    // SNIPPET:END {some_cool_snippet_name}
-    */
-    let snippet_pattern_active = regex::RegexBuilder::new(
-        r"(// SNIPPET:BEGIN \{(?P<BEGIN>.*?)\}(\$\{(?P<COMMENT>.*?)\})?(?P<SNIPPET>.*?)// SNIPPET:END \{(?P<END>.*?)\})",
-    )
-    .dot_matches_new_line(true)
-    .build()
-    .unwrap();
-
-    let snippet_pattern_inactive = regex::RegexBuilder::new(
-        r"(// _SNIPPET:BEGIN \{(?P<BEGIN>.*?)\}(\$\{(?P<COMMENT>.*?)\})?(?P<SNIPPET>.*?)// _SNIPPET:END \{(?P<END>.*?)\})",
-    )
-    .dot_matches_new_line(true)
-    .build()
-    .unwrap();
-
-    // Finding snippet inclusions that look something like this:
-    /*
-    \lstinputlisting[...]{some_cool_snippet_name.cpp} where the [...] part is optional
-    */
-    let include_pattern =
-        regex::RegexBuilder::new(r"(\\lstinputlisting.*?\{.*/(?P<SNIPPET_NAME>.*?)\.cpp.*?\})")
-            .dot_matches_new_line(false)
-            .build()
-            .unwrap();
-
-    let mut snippets = HashMap::new();
-    for file in &source_files {
-        let text = fs::read_to_string(&file).context(format!("{:?}", file));
-        if let Ok(text) = text {
-            for captures in snippet_pattern_active.captures_iter(&text) {
-                let snippet = Snippet::new(
-                    captures.name("BEGIN").map(|hit| hit.as_str()),
-                    captures.name("SNIPPET").map(|hit| hit.as_str()),
+/// Collect every occurrence of a `multiple(true)` argument, or an empty
+/// `Vec` when none were given.
+fn values(matches: &ArgMatches, name: &str) -> Vec<String> {
+    matches
+        .values_of(name)
+        .map(|values| values.map(str::to_owned).collect())
+        .unwrap_or_default()
+}
+
+fn parse_command(arguments: &ArgMatches) -> Result<Command> {
+    match arguments.subcommand() {
+        ("list", Some(sub)) => Ok(Command::List {
+            source_directory: PathBuf::from(sub.value_of("Source").unwrap()),
+            target_directory: PathBuf::from(sub.value_of("Target").unwrap()),
+            latex_directory: PathBuf::from(sub.value_of("LaTeX").unwrap()),
+            include: values(sub, "include"),
+            exclude: values(sub, "exclude"),
+        }),
+        ("status", Some(sub)) => Ok(Command::Status {
+            source_directory: PathBuf::from(sub.value_of("Source").unwrap()),
+            target_directory: PathBuf::from(sub.value_of("Target").unwrap()),
+            latex_directory: PathBuf::from(sub.value_of("LaTeX").unwrap()),
+            include: values(sub, "include"),
+            exclude: values(sub, "exclude"),
+        }),
+        ("extract", Some(sub)) => Ok(Command::Extract {
+            source_directory: PathBuf::from(sub.value_of("Source").unwrap()),
+            target_directory: PathBuf::from(sub.value_of("Target").unwrap()),
+            include: values(sub, "include"),
+            exclude: values(sub, "exclude"),
+        }),
+        ("new", Some(sub)) => Ok(Command::New {
+            source_directory: PathBuf::from(sub.value_of("Source").unwrap()),
+            file: PathBuf::from(sub.value_of("file").unwrap()),
+            name: sub.value_of("name").unwrap().to_owned(),
+        }),
+        ("add", Some(sub)) => Ok(Command::Add {
+            source_directory: PathBuf::from(sub.value_of("Source").unwrap()),
+            file: PathBuf::from(sub.value_of("file").unwrap()),
+            name: sub.value_of("name").unwrap().to_owned(),
+            start: sub.value_of("start").unwrap().parse()?,
+            end: sub.value_of("end").unwrap().parse()?,
+        }),
+        ("prune", Some(sub)) => Ok(Command::Prune {
+            source_directory: PathBuf::from(sub.value_of("Source").unwrap()),
+            target_directory: PathBuf::from(sub.value_of("Target").unwrap()),
+        }),
+        _ => Err(anyhow::anyhow!(
+            "No operation given. Run with --help to see available subcommands."
+        )),
+    }
+}
+
+/// Scan `source_directory` for active and inactive `SNIPPET:BEGIN`/`END`
+/// blocks, across every language profile in `config`. This is the subset of
+/// scanning needed by any operation that only cares about what's currently
+/// tagged in source.
+fn scan_source(
+    source_directory: &Path,
+    config: &Config,
+    filters: &glob::Filters,
+) -> Result<HashMap<String, Snippet>> {
+    let mut snippets: HashMap<String, Snippet> = HashMap::new();
+
+    for profile in &config.profiles {
+        let source_files =
+            files_matching(source_directory, profile.extensions.clone(), filters, false);
+        let (snippet_pattern_active, snippet_pattern_inactive) = profile.snippet_patterns()?;
+
+        // Parse every file once, but keep actives and inactives in separate
+        // buckets so they're merged in the same two passes the scanner used
+        // before parsing moved into `parsing::parse_snippets`: every active
+        // occurrence first (appending fragments on name collision), then
+        // every inactive occurrence (which only ever downgrades `.active`).
+        let mut active_snippets = Vec::new();
+        let mut inactive_snippets = Vec::new();
+
+        for file in &source_files {
+            let text = fs::read_to_string(&file).context(format!("{:?}", file));
+            if let Ok(text) = text {
+                for snippet in parse_snippets(
+                    &text,
+                    &snippet_pattern_active,
+                    &snippet_pattern_inactive,
                     Some(file.clone()),
-                    captures.name("END").map(|hit| hit.as_str()),
-                    captures.name("COMMENT").map(|hit| hit.as_str()),
-                    true,
-                    true,
-                    false,
-                    false,
-                );
-                if let Ok(snippet) = snippet {
-                    snippets.insert(snippet.name.clone(), snippet);
-                } else {
-                    eprintln!("{:#?}", snippet);
+                    Some(&profile.extracted_extension),
+                ) {
+                    if snippet.active {
+                        active_snippets.push(snippet);
+                    } else {
+                        inactive_snippets.push(snippet);
+                    }
                 }
+            } else {
+                eprintln!("{:#?}", text);
             }
-        } else {
-            eprintln!("{:#?}", text);
+        }
+
+        for mut snippet in active_snippets {
+            match snippets.entry(snippet.name.clone()) {
+                std::collections::hash_map::Entry::Occupied(mut existing) => {
+                    existing.get_mut().content.append(&mut snippet.content);
+                }
+                std::collections::hash_map::Entry::Vacant(vacant) => {
+                    vacant.insert(snippet);
+                }
+            }
+        }
+
+        for snippet in inactive_snippets {
+            snippets
+                .entry(snippet.name.clone())
+                .or_insert(snippet)
+                .active = false;
         }
     }
 
-    for file in &source_files {
-        let text = fs::read_to_string(&file).context(format!("{:#?}", file));
-        if let Ok(text) = text {
-            for captures in snippet_pattern_inactive.captures_iter(&text) {
-                let snippet = Snippet::new(
-                    captures.name("BEGIN").map(|hit| hit.as_str()),
-                    captures.name("SNIPPET").map(|hit| hit.as_str()),
-                    Some(file.clone()),
-                    captures.name("END").map(|hit| hit.as_str()),
-                    captures.name("COMMENT").map(|hit| hit.as_str()),
-                    false,
-                    true,
-                    false,
-                    false,
-                )
-                .context("Failed at creating snippet from LaTeX include statement.");
+    warn_on_duplicate_merges(&snippets);
 
-                if let Ok(snippet) = snippet {
-                    snippets
-                        .entry(snippet.name.clone())
-                        .or_insert(snippet)
-                        .active = false;
-                } else {
-                    eprint!("{:#?}", snippet);
+    Ok(snippets)
+}
+
+/// A snippet made up of more than one fragment is usually the intended
+/// multi-region case this module exists for, but it's also exactly what an
+/// accidental copy-pasted `SNIPPET:BEGIN {name}` looks like — whether the
+/// copy landed in an unrelated file or was pasted again further down the
+/// same one. Warn rather than error, since merging is now the supported
+/// behavior, but keep the signal visible either way.
+fn warn_on_duplicate_merges(snippets: &HashMap<String, Snippet>) {
+    let mut merged: Vec<_> = snippets
+        .values()
+        .filter(|snippet| snippet.active)
+        .filter_map(|snippet| {
+            let source_files: Vec<_> = snippet
+                .content
+                .iter()
+                .map(|fragment| &fragment.source_file)
+                .collect();
+            let mut distinct_files = source_files.clone();
+            distinct_files.sort();
+            distinct_files.dedup();
+            let cross_file = distinct_files.len() > 1;
+            (source_files.len() > 1).then_some((snippet.name.as_str(), source_files, cross_file))
+        })
+        .collect();
+    merged.sort_by_key(|(name, _, _)| *name);
+
+    for (name, source_files, cross_file) in merged {
+        let locations = source_files
+            .iter()
+            .map(|file| format!("    {:?}", file))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let scope = if cross_file {
+            "regions from multiple files"
+        } else {
+            "multiple regions in the same file"
+        };
+        bunt::println!(
+            "{[yellow]}",
+            format!(
+                "Snippet \"{}\" merges {}. If this isn't intentional, check for a duplicate tag:\n{}",
+                name, scope, locations
+            )
+        );
+    }
+}
+
+/// Full scan across source, target and LaTeX directories, used by `list`/`status`.
+fn scan_full(
+    source_directory: &Path,
+    target_directory: &Path,
+    latex_directory: &Path,
+    config: &Config,
+    filters: &glob::Filters,
+) -> Result<Vec<Snippet>> {
+    let mut snippets = scan_source(source_directory, config, filters)?;
+
+    let tex_files = files_with_extension(latex_directory, vec!["tex".into()], false);
+
+    for profile in &config.profiles {
+        let include_pattern = profile.include_pattern()?;
+        for file in &tex_files {
+            let text = fs::read_to_string(&file).context(format!("{:?}", file));
+            if let Ok(text) = text {
+                for snippet_name in parse_includes(&text, &include_pattern) {
+                    let snippet = Snippet::new(
+                        Some(&snippet_name),
+                        None,
+                        None,
+                        Some(&snippet_name),
+                        None,
+                        Some(&profile.extracted_extension),
+                        false,
+                        false,
+                        true,
+                        false,
+                    )
+                    .context("Failed at creating snippet from LaTeX include statement.");
+
+                    if let Ok(snippet) = snippet {
+                        snippets
+                            .entry(snippet.name.clone())
+                            .or_insert(snippet)
+                            .latex = true;
+                    } else {
+                        eprintln!("{:#?}", snippet);
+                    }
                 }
+            } else {
+                eprintln!("{:#?}", text);
             }
-        } else {
-            eprintln!("{:#?}", text);
         }
-    }
 
-    for file in tex_files {
-        let text = fs::read_to_string(&file).context(format!("{:?}", file));
-        if let Ok(text) = text {
-            for captures in include_pattern.captures_iter(&text) {
-                let snippet_name = captures.name("SNIPPET_NAME").map(|hit| hit.as_str());
+        let snippet_files = files_with_extension(
+            target_directory,
+            vec![profile.extracted_extension.clone()],
+            false,
+        );
+        for file in snippet_files {
+            let snippet_name = file
+                .file_stem()
+                .map(|name| name.to_str().map(|name| name.to_owned()))
+                .flatten()
+                .context("Unable to obtain snippet name from snippet file.");
+            if let Ok(snippet_name) = snippet_name {
                 let snippet = Snippet::new(
-                    snippet_name,
+                    Some(&snippet_name),
                     None,
                     None,
-                    snippet_name,
+                    Some(&snippet_name),
                     None,
+                    Some(&profile.extracted_extension),
                     false,
                     false,
-                    true,
                     false,
+                    true,
                 )
-                .context("Failed at creating snippet from LaTeX include statement.");
+                .context("Failed at creating snippet from extracted snippet file.");
 
                 if let Ok(snippet) = snippet {
                     snippets
                         .entry(snippet.name.clone())
                         .or_insert(snippet)
-                        .latex = true;
+                        .extracted = true;
                 } else {
                     eprintln!("{:#?}", snippet);
                 }
-            }
-        } else {
-            eprintln!("{:#?}", text);
-        }
-    }
-
-    for file in snippet_files {
-        let snippet_name = file
-            .file_stem()
-            .map(|name| name.to_str().map(|name| name.to_owned()))
-            .flatten()
-            .context("Unable to obtain snippet name from snippet file.");
-        if let Ok(snippet_name) = snippet_name {
-            let snippet = Snippet::new(
-                Some(&snippet_name),
-                None,
-                None,
-                Some(&snippet_name),
-                None,
-                false,
-                false,
-                false,
-                true,
-            )
-            .context("Failed at creating snippet from extracted snippet file.");
-
-            if let Ok(snippet) = snippet {
-                snippets
-                    .entry(snippet.name.clone())
-                    .or_insert(snippet)
-                    .extracted = true;
             } else {
-                eprintln!("{:#?}", snippet);
+                eprintln!("{:#?}", snippet_name);
             }
-        } else {
-            eprintln!("{:#?}", snippet_name);
         }
     }
 
-    // 1. List found snippets and inclusions
     let mut snippets: Vec<_> = snippets.into_iter().map(|entry| entry.1).collect();
     snippets.sort();
+    Ok(snippets)
+}
 
+fn print_report(snippets: &[Snippet]) {
     let mut count_width = 0;
     let mut snippet_name_width = "Snippet name:".len();
     let mut file_name_width = "Source file:".len();
@@ -329,11 +597,39 @@ fn main() {
     }
 
     bunt::println!("{[underline]}", header_top);
+}
 
-    // 2. Update snippet files
-    if !arguments.is_present("Extract") {
-        return;
-    }
+fn cmd_list(
+    source_directory: &Path,
+    target_directory: &Path,
+    latex_directory: &Path,
+    include: &[String],
+    exclude: &[String],
+) -> Result<()> {
+    let config = Config::load(source_directory)?;
+    let filters = glob::Filters::new(include, exclude)?;
+    let snippets = scan_full(
+        source_directory,
+        target_directory,
+        latex_directory,
+        &config,
+        &filters,
+    )?;
+    print_report(&snippets);
+    Ok(())
+}
+
+fn cmd_extract(
+    source_directory: &Path,
+    target_directory: &Path,
+    include: &[String],
+    exclude: &[String],
+) -> Result<()> {
+    let config = Config::load(source_directory)?;
+    let filters = glob::Filters::new(include, exclude)?;
+    let snippets = scan_source(source_directory, &config, &filters)?;
+    let mut snippets: Vec<_> = snippets.into_iter().map(|entry| entry.1).collect();
+    snippets.sort();
 
     for snippet in snippets {
         if !snippet.source {
@@ -348,7 +644,7 @@ fn main() {
         }
 
         let mut extraction_path = target_directory.join(&snippet.name);
-        extraction_path.set_extension("cpp");
+        extraction_path.set_extension(snippet.extension.as_deref().unwrap_or("cpp"));
 
         let file = if snippet.active {
             OpenOptions::new()
@@ -365,7 +661,20 @@ fn main() {
         };
 
         if let Ok(mut file) = file {
-            if let Some(content) = snippet.content {
+            if !snippet.content.is_empty() {
+                let separator = snippet
+                    .extension
+                    .as_deref()
+                    .and_then(|extension| config.profile_for_extracted_extension(extension))
+                    .map(LanguageProfile::fragment_separator)
+                    .unwrap_or("\n");
+                let content = snippet
+                    .content
+                    .iter()
+                    .map(|fragment| fragment.content.as_str())
+                    .collect::<Vec<_>>()
+                    .join(separator);
+
                 let write_status = file.write_all(content.as_bytes());
                 if write_status.is_ok() {
                     bunt::println!(
@@ -395,39 +704,184 @@ fn main() {
             continue;
         }
     }
+
+    Ok(())
 }
 
-fn files_with_extension(
+/// Find the profile that claims `file`'s extension, so `new`/`add` scaffold
+/// comments in the right style instead of assuming C++ `//`.
+fn profile_for_file<'a>(config: &'a Config, file: &Path) -> Result<&'a LanguageProfile> {
+    let extension = file
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .context(format!("File \"{:?}\" has no extension.", file))?;
+    config.profile_for_extension(extension).context(format!(
+        "No language profile configured for \".{}\" files.",
+        extension
+    ))
+}
+
+fn cmd_new(source_directory: &Path, file: &Path, name: &str) -> Result<()> {
+    let config = Config::load(source_directory)?;
+    let profile = profile_for_file(&config, file)?;
+
+    let mut content = fs::read_to_string(file).context(format!("{:?}", file))?;
+    if !content.is_empty() && !content.ends_with('\n') {
+        content.push('\n');
+    }
+    content.push_str(&format!(
+        "{}\n{}\n",
+        profile.wrap_tag(&format!("SNIPPET:BEGIN {{{}}}", name)),
+        profile.wrap_tag(&format!("SNIPPET:END {{{}}}", name)),
+    ));
+
+    fs::write(file, content)
+        .context(format!("Unable to write scaffolded snippet to {:?}", file))?;
+    bunt::println!("Snippet \"{}\" scaffolded into \"{:?}\".", name, file);
+    Ok(())
+}
+
+fn cmd_add(
+    source_directory: &Path,
+    file: &Path,
+    name: &str,
+    start: usize,
+    end: usize,
+) -> Result<()> {
+    let config = Config::load(source_directory)?;
+    let profile = profile_for_file(&config, file)?;
+
+    let content = fs::read_to_string(file).context(format!("{:?}", file))?;
+    // `str::lines` strips both `\n` and `\r\n`, so rejoining with a bare `\n`
+    // would silently turn a CRLF file into LF. Rejoin with whichever ending
+    // the file already used.
+    let line_ending = if content.contains("\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    };
+    let mut lines: Vec<&str> = content.lines().collect();
+
+    if start == 0 || end < start || end > lines.len() {
+        return Err(anyhow::anyhow!(
+            "Invalid region {}..={} for file \"{:?}\" with {} lines.",
+            start,
+            end,
+            file,
+            lines.len()
+        ));
+    }
+
+    let begin_tag = profile.wrap_tag(&format!("SNIPPET:BEGIN {{{}}}", name));
+    let end_tag = profile.wrap_tag(&format!("SNIPPET:END {{{}}}", name));
+
+    lines.insert(end, &end_tag);
+    lines.insert(start - 1, &begin_tag);
+
+    let mut new_content = lines.join(line_ending);
+    if content.ends_with('\n') {
+        new_content.push_str(line_ending);
+    }
+
+    fs::write(file, new_content)
+        .context(format!("Unable to write snippet region to {:?}", file))?;
+    bunt::println!(
+        "Snippet \"{}\" wraps lines {}..={} of \"{:?}\".",
+        name,
+        start,
+        end,
+        file
+    );
+    Ok(())
+}
+
+fn cmd_prune(source_directory: &Path, target_directory: &Path) -> Result<()> {
+    let config = Config::load(source_directory)?;
+    let filters = glob::Filters::new(&[], &[])?;
+    let snippets = scan_source(source_directory, &config, &filters)?;
+    let extracted_extensions = config
+        .profiles
+        .iter()
+        .map(|profile| profile.extracted_extension.clone())
+        .collect();
+    let extracted_files = files_with_extension(target_directory, extracted_extensions, false);
+    let active_names: std::collections::HashSet<&str> = snippets
+        .values()
+        .filter(|snippet| snippet.active)
+        .map(|snippet| snippet.name.as_str())
+        .collect();
+
+    for file in extracted_files {
+        let name = file.file_stem().and_then(|name| name.to_str());
+        let stale = match name {
+            Some(name) => !active_names.contains(name),
+            None => false,
+        };
+
+        if stale {
+            match fs::remove_file(&file) {
+                Ok(()) => bunt::println!(
+                    "{[yellow]}",
+                    format!("Pruned stale snippet file \"{:?}\".", file)
+                ),
+                Err(error) => eprintln!("Unable to prune \"{:?}\": {:#}", file, error),
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// True when `path`'s extension matches any of `extensions` (compared
+/// case-insensitively unless `case_sensitive`).
+fn has_extension(path: &Path, extensions: &[String], case_sensitive: bool) -> bool {
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some(extension) => extensions.iter().any(|candidate| {
+            if case_sensitive {
+                candidate == extension
+            } else {
+                candidate.eq_ignore_ascii_case(extension)
+            }
+        }),
+        None => false,
+    }
+}
+
+/// Walk `root_directory` for files of the given `extensions`, additionally
+/// narrowed by `filters` when any include/exclude globs were given. The
+/// extension check always applies, so a glob never pulls in files from a
+/// different profile than the one that's currently scanning.
+fn files_matching(
     root_directory: &Path,
-    mut extensions: Vec<String>,
+    extensions: Vec<String>,
+    filters: &glob::Filters,
     case_sensitive: bool,
 ) -> Vec<PathBuf> {
-    if !case_sensitive {
-        for x in extensions.iter_mut() {
-            x.make_ascii_lowercase()
-        }
+    if filters.is_empty() {
+        return files_with_extension(root_directory, extensions, case_sensitive);
     }
 
+    WalkDir::new(root_directory)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file())
+        .map(|entry| entry.into_path())
+        .filter(|path| filters.matches(path))
+        .filter(|path| has_extension(path, &extensions, case_sensitive))
+        .collect()
+}
+
+fn files_with_extension(
+    root_directory: &Path,
+    extensions: Vec<String>,
+    case_sensitive: bool,
+) -> Vec<PathBuf> {
     WalkDir::new(root_directory)
         .into_iter()
         .filter_entry(|entry| {
             entry.file_type().is_dir()
                 || (entry.file_type().is_file()
-                    && extensions
-                        .iter()
-                        .map(|extension| Some(extension.to_string()))
-                        .any(|x| {
-                            x == entry
-                                .path()
-                                .extension()
-                                .map(|extension| {
-                                    if !case_sensitive {
-                                        extension.to_owned().make_ascii_lowercase();
-                                    }
-                                    extension.to_str().map(|x| x.to_string())
-                                })
-                                .flatten()
-                        }))
+                    && has_extension(entry.path(), &extensions, case_sensitive))
         })
         .collect::<Vec<_>>()
         .into_iter()
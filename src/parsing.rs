@@ -0,0 +1,233 @@
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+
+/// One `SNIPPET:BEGIN`/`END` block's content. A `Snippet` can be made up of
+/// several fragments, tagged under the same name across different places
+/// (or files), which get concatenated in document order on extraction.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Fragment {
+    pub content: String,
+    pub source_file: PathBuf,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Snippet {
+    pub name: String,
+    pub content: Vec<Fragment>,
+    pub source_file: Option<PathBuf>,
+    pub comment: Option<String>,
+    /// Extension the extracted file should use, taken from the
+    /// `extracted_extension` of whichever `LanguageProfile` tagged this
+    /// snippet.
+    pub extension: Option<String>,
+    pub active: bool,
+    pub source: bool,
+    pub latex: bool,
+    pub extracted: bool,
+}
+
+impl Ord for Snippet {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.name.cmp(&other.name)
+    }
+}
+
+impl PartialOrd for Snippet {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Snippet {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        begin: Option<&str>,
+        content: Option<&str>,
+        source_file: Option<PathBuf>,
+        end: Option<&str>,
+        comment: Option<&str>,
+        extension: Option<&str>,
+        active: bool,
+        source: bool,
+        latex: bool,
+        extracted: bool,
+    ) -> Result<Self> {
+        let begin = begin.context("Snippet begin tag has no name.")?;
+        let end = end.context("Snippet end tag has no name.")?;
+
+        (begin == end)
+            .then_some(Self {
+                name: begin.to_owned(),
+                content: content
+                    .map(|string| {
+                        vec![Fragment {
+                            content: string.to_owned(),
+                            source_file: source_file.clone().unwrap_or_default(),
+                        }]
+                    })
+                    .unwrap_or_default(),
+                source_file,
+                comment: comment.map(|string| string.to_owned()),
+                extension: extension.map(|string| string.to_owned()),
+                active,
+                source,
+                latex,
+                extracted,
+            })
+            .context(format!(
+                "Snippet with mismatched begin and end tags\n\"{}\" != \"{}\"",
+                begin, end
+            ))
+    }
+}
+
+/// Parse every active and inactive `SNIPPET:BEGIN`/`END` block out of `text`,
+/// tagging each resulting `Snippet` with `source_file` and
+/// `extracted_extension`. Pure: no filesystem access, so `scan_source` can
+/// fold the result into its collision-merging `HashMap` and the property
+/// tests below (and the `fuzz` target) can drive it directly with arbitrary
+/// text.
+pub fn parse_snippets(
+    text: &str,
+    active_pattern: &regex::Regex,
+    inactive_pattern: &regex::Regex,
+    source_file: Option<PathBuf>,
+    extracted_extension: Option<&str>,
+) -> Vec<Snippet> {
+    let mut snippets = Vec::new();
+
+    for captures in active_pattern.captures_iter(text) {
+        if let Ok(snippet) = Snippet::new(
+            captures.name("BEGIN").map(|hit| hit.as_str()),
+            captures.name("SNIPPET").map(|hit| hit.as_str()),
+            source_file.clone(),
+            captures.name("END").map(|hit| hit.as_str()),
+            captures.name("COMMENT").map(|hit| hit.as_str()),
+            extracted_extension,
+            true,
+            true,
+            false,
+            false,
+        ) {
+            snippets.push(snippet);
+        }
+    }
+
+    for captures in inactive_pattern.captures_iter(text) {
+        if let Ok(snippet) = Snippet::new(
+            captures.name("BEGIN").map(|hit| hit.as_str()),
+            captures.name("SNIPPET").map(|hit| hit.as_str()),
+            source_file.clone(),
+            captures.name("END").map(|hit| hit.as_str()),
+            captures.name("COMMENT").map(|hit| hit.as_str()),
+            extracted_extension,
+            false,
+            true,
+            false,
+            false,
+        ) {
+            snippets.push(snippet);
+        }
+    }
+
+    snippets
+}
+
+/// Parse every `\lstinputlisting{...name.ext}` include out of `text`, in
+/// document order. Pure, for the same reason as `parse_snippets`.
+pub fn parse_includes(text: &str, include_pattern: &regex::Regex) -> Vec<String> {
+    include_pattern
+        .captures_iter(text)
+        .filter_map(|captures| {
+            captures
+                .name("SNIPPET_NAME")
+                .map(|hit| hit.as_str().to_owned())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    /// Build the same active/inactive/include patterns `LanguageProfile`
+    /// would for the `//`-comment, `.cpp`-extension default profile, without
+    /// depending on the `config` module.
+    fn test_patterns() -> (regex::Regex, regex::Regex, regex::Regex) {
+        let active = regex::RegexBuilder::new(
+            r"(// SNIPPET:BEGIN \{(?P<BEGIN>.*?)\}(\$\{(?P<COMMENT>.*?)\})?(?P<SNIPPET>.*?)// SNIPPET:END \{(?P<END>.*?)\})",
+        )
+        .dot_matches_new_line(true)
+        .build()
+        .unwrap();
+
+        let inactive = regex::RegexBuilder::new(
+            r"(// _SNIPPET:BEGIN \{(?P<BEGIN>.*?)\}(\$\{(?P<COMMENT>.*?)\})?(?P<SNIPPET>.*?)// _SNIPPET:END \{(?P<END>.*?)\})",
+        )
+        .dot_matches_new_line(true)
+        .build()
+        .unwrap();
+
+        let include =
+            regex::RegexBuilder::new(r"(\\lstinputlisting.*?\{.*/(?P<SNIPPET_NAME>.*?)\.cpp.*?\})")
+                .build()
+                .unwrap();
+
+        (active, inactive, include)
+    }
+
+    proptest! {
+        /// `Snippet::new` only succeeds when the begin and end tags agree,
+        /// regardless of what content or comment sits between them.
+        #[test]
+        fn snippet_only_produced_when_begin_matches_end(
+            begin in "[a-zA-Z0-9_]{0,12}",
+            end in "[a-zA-Z0-9_]{0,12}",
+            content in ".{0,64}",
+        ) {
+            let snippet = Snippet::new(
+                Some(&begin),
+                Some(&content),
+                None,
+                Some(&end),
+                None,
+                None,
+                true,
+                true,
+                false,
+                false,
+            );
+            prop_assert_eq!(snippet.is_ok(), begin == end);
+        }
+
+        /// A single-fragment snippet's content round-trips byte-for-byte
+        /// through the same join `cmd_extract` uses at extraction time.
+        #[test]
+        fn extraction_round_trips_single_fragment_content(content in ".{0,256}") {
+            let snippet = Snippet::new(
+                Some("x"), Some(&content), None, Some("x"), None, None, true, true, false, false,
+            ).unwrap();
+
+            let joined = snippet
+                .content
+                .iter()
+                .map(|fragment| fragment.content.as_str())
+                .collect::<Vec<_>>()
+                .join("\n");
+
+            prop_assert_eq!(joined, content);
+        }
+
+        /// Neither parser should panic or hang on arbitrary, likely
+        /// malformed, input: unmatched braces, mismatched tags, truncated
+        /// `\lstinputlisting` calls, etc.
+        #[test]
+        fn parsing_never_panics(text in ".{0,512}") {
+            let (active, inactive, include) = test_patterns();
+            let _ = parse_snippets(&text, &active, &inactive, None, Some("cpp"));
+            let _ = parse_includes(&text, &include);
+        }
+    }
+}